@@ -479,7 +479,7 @@ fn remove_alias_success() {
     let config = r#"{"aliases":{"test":{"command_type":{"Simple":"cargo --version"},"description":null,"created":"2025-10-20"}}}"#;
     fs::write(&config_path, config).expect("write config");
 
-    cmd.args(["--remove", "test"])
+    cmd.args(["--remove", "test", "--force"])
         .assert()
         .success()
         .stdout(predicate::str::contains("Removed alias 'test'"));
@@ -799,6 +799,30 @@ fn help_with_no_examples() {
         .stdout(predicate::str::contains("📖 EXAMPLES:").not());
 }
 
+#[test]
+fn help_lists_substitution_tokens() {
+    let (mut cmd, home) = command_with_home();
+    let _ = alias_config_path(&home);
+
+    let assert = cmd.arg("--help").assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    for token in [
+        "${@:N}",
+        "$#",
+        "$?",
+        "$DATE",
+        "$TIME",
+        "$TIMESTAMP",
+        "$CWD",
+        "$HOME",
+        "$RANDOM",
+        "$UUID",
+        "{{NAME}}",
+    ] {
+        assert!(stdout.contains(token), "--help does not mention {}", token);
+    }
+}
+
 #[test]
 fn add_alias_unknown_flag() {
     let (mut cmd, home) = command_with_home();
@@ -917,8 +941,1769 @@ fn test_save_if_saved_alias_creation_and_display() {
     rm_cmd.env("HOME", home.path());
     rm_cmd.env("USERPROFILE", home.path());
     rm_cmd
-        .args(["--remove", "test_save_e2e"])
+        .args(["--remove", "test_save_e2e", "--force"])
         .assert()
         .success()
         .stdout(predicate::str::contains("Removed alias"));
 }
+
+#[test]
+fn remove_all_with_force_clears_aliases_and_reports_backup() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+
+    let config = r#"
+{
+  "aliases": {
+    "one": {
+      "command_type": { "Simple": "echo one" },
+      "description": null,
+      "created": "2025-10-20"
+    }
+  }
+}
+"#;
+    fs::write(&config_path, config).expect("write config");
+
+    cmd.args(["--remove", "--all", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 1 aliases"))
+        .stdout(predicate::str::contains("Backup written to:"));
+
+    let written = fs::read_to_string(&config_path).expect("read config");
+    assert!(!written.contains("echo one"));
+    assert!(home.path().join(".alias-mgr").join("backups").exists());
+}
+
+#[test]
+fn serve_json_run_streams_events_then_response() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+
+    let config = r#"{
+  "aliases": {
+    "ver": {
+      "command_type": { "Simple": "cargo --version" },
+      "description": null,
+      "created": "2025-10-20"
+    }
+  }
+}"#;
+    fs::write(&config_path, config).expect("write config");
+
+    let output = cmd
+        .arg("--serve-json")
+        .write_stdin("{\"id\":7,\"op\":\"run\",\"name\":\"ver\"}\n{\"id\":8,\"op\":\"list\"}\n")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let lines: Vec<serde_json::Value> = String::from_utf8(output)
+        .expect("utf-8 output")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("every line is JSON"))
+        .collect();
+
+    assert!(lines
+        .iter()
+        .any(|l| l["event"] == "stdout" && l["line"].as_str().unwrap().contains("cargo")));
+    let run_response = lines
+        .iter()
+        .find(|l| l["id"] == 7 && l.get("ok").is_some())
+        .expect("run response");
+    assert_eq!(run_response["result"]["exit_code"], 0);
+    assert_eq!(lines.last().unwrap()["id"], 8);
+}
+
+#[test]
+fn list_origin_filter_and_long_output() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+
+    let config = r#"{
+  "aliases": {
+    "mine": {
+      "command_type": { "Simple": "echo mine" },
+      "description": null,
+      "created": "2025-10-20",
+      "origin": "manual"
+    },
+    "theirs": {
+      "command_type": { "Simple": "echo theirs" },
+      "description": null,
+      "created": "2025-10-20",
+      "origin": "pull:0x4d44/a@1a2b3c4"
+    }
+  }
+}"#;
+    fs::write(&config_path, config).expect("write config");
+
+    cmd.args(["--list", "--origin", "pull", "--long"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("theirs"))
+        .stdout(predicate::str::contains("origin: pull:0x4d44/a@1a2b3c4"))
+        .stdout(predicate::str::contains("mine").not());
+}
+
+#[test]
+fn completions_dynamic_lists_alias_names_by_prefix() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+
+    let config = r#"
+{
+  "aliases": {
+    "build": { "command_type": { "Simple": "cargo build" }, "created": "2025-10-20" },
+    "bench": { "command_type": { "Simple": "cargo bench" }, "created": "2025-10-20" },
+    "test": { "command_type": { "Simple": "cargo test" }, "created": "2025-10-20" }
+  }
+}
+"#;
+    fs::write(&config_path, config).expect("write config file");
+
+    cmd.args(["--completions-dynamic", "b"])
+        .assert()
+        .success()
+        .stdout("bench\nbuild\n");
+}
+
+#[test]
+fn snippet_is_expanded_when_alias_runs() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+
+    let config = r#"
+{
+  "aliases": {
+    "check": {
+      "command_type": { "Chain": { "commands": [
+        { "command": "use:version", "operator": null }
+      ], "parallel": false } },
+      "created": "2025-10-20"
+    }
+  },
+  "snippets": {
+    "version": [ { "command": "cargo --version", "operator": null } ]
+  }
+}
+"#;
+    fs::write(&config_path, config).expect("write config file");
+
+    cmd.arg("check")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("cargo "));
+}
+
+#[test]
+fn rename_refuses_existing_target_without_confirmation() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+
+    let config = r#"
+{
+  "aliases": {
+    "old": { "command_type": { "Simple": "cargo --version" }, "created": "2025-10-20" },
+    "new": { "command_type": { "Simple": "cargo build" }, "created": "2025-10-20" }
+  }
+}
+"#;
+    fs::write(&config_path, config).expect("write config file");
+
+    cmd.args(["--rename", "old", "new"])
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Alias not renamed."));
+
+    let saved = fs::read_to_string(&config_path).expect("read config");
+    assert!(saved.contains("\"old\""));
+}
+
+#[test]
+fn panic_writes_local_crash_report_without_arguments() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+
+    cmd.args(["--__test-panic", "hunter2-secret"])
+        .assert()
+        .code(70)
+        .stderr(predicate::str::contains("Crash report written to"));
+
+    let crash_dir = config_path.with_file_name("crash");
+    let reports: Vec<_> = fs::read_dir(&crash_dir)
+        .expect("crash directory exists")
+        .map(|entry| entry.expect("dir entry").path())
+        .collect();
+    assert_eq!(reports.len(), 1);
+    let report = fs::read_to_string(&reports[0]).expect("read crash report");
+    assert!(report.contains("controlled panic for crash report test"));
+    assert!(report.contains("Subcommand: --__test-panic"));
+    assert!(report.contains(&version_fragment()));
+    assert!(!report.contains("hunter2-secret"));
+}
+
+#[test]
+fn paths_json_lists_config_under_home() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+    fs::write(&config_path, r#"{"aliases":{}}"#).expect("write config file");
+
+    let output = cmd.args(["--paths", "--json"]).output().expect("run a");
+    assert!(output.status.success());
+    let listed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json output");
+    let config = listed
+        .as_array()
+        .expect("array")
+        .iter()
+        .find(|entry| entry["label"] == "config")
+        .expect("config entry");
+    assert_eq!(config["path"], config_path.display().to_string());
+    assert_eq!(config["exists"], true);
+    assert_eq!(config["size"], 14);
+}
+
+#[test]
+fn remove_glob_dry_run_leaves_config_untouched() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+    let config = r#"{"aliases":{"tmp-1":{"command_type":{"Simple":"cargo --version"},"created":"2025-10-20"},"keep":{"command_type":{"Simple":"cargo --version"},"created":"2025-10-20"}}}"#;
+    fs::write(&config_path, config).expect("write config");
+
+    cmd.args(["--remove", "tmp-*", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would remove alias 'tmp-1'"));
+
+    assert_eq!(
+        fs::read_to_string(&config_path).expect("read config"),
+        config
+    );
+}
+
+#[test]
+fn remove_without_terminal_requires_force() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+    let config = r#"{"aliases":{"deploy":{"command_type":{"Simple":"cargo --version"},"created":"2025-10-20"}}}"#;
+    fs::write(&config_path, config).expect("write config");
+
+    cmd.args(["--remove", "deploy"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass --force"));
+
+    assert_eq!(
+        fs::read_to_string(&config_path).expect("read config"),
+        config
+    );
+}
+
+#[test]
+fn add_stdin_command_stores_input_verbatim() {
+    let commands = [
+        r#"git commit -m "$1" && git push"#,
+        r#"printf 'a\tb\\c' "it's \"quoted\"" $@"#,
+        r"dir C:\Users\me\*.txt",
+    ];
+    for command in commands {
+        let (mut cmd, home) = command_with_home();
+        let config_path = alias_config_path(&home);
+
+        cmd.args(["--add", "piped", "--stdin-command", "--desc", "from stdin"])
+            .write_stdin(format!("{}\n", command))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("program:"));
+
+        let config: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&config_path).expect("read config"))
+                .expect("config is JSON");
+        assert_eq!(
+            config["aliases"]["piped"]["command_type"]["Simple"],
+            serde_json::Value::String(command.to_string())
+        );
+        assert_eq!(config["aliases"]["piped"]["description"], "from stdin");
+    }
+}
+
+#[test]
+fn add_stdin_command_rejects_multiple_lines() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+
+    cmd.args(["--add", "two", "--stdin-command"])
+        .write_stdin("cargo build\ncargo test\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--and"));
+
+    assert!(!config_path.exists());
+}
+
+#[test]
+fn disabled_alias_is_listed_and_refused() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+    fs::write(
+        &config_path,
+        r#"{"aliases":{"ver":{"command_type":{"Simple":"cargo --version"},"created":"2025-10-20"}}}"#,
+    )
+    .expect("write config");
+
+    cmd.args(["--disable", "ver"]).assert().success();
+
+    let mut list = Command::cargo_bin("a").expect("binary exists");
+    list.env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .arg("--list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[disabled]"));
+
+    let mut run = Command::cargo_bin("a").expect("binary exists");
+    run.env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .arg("ver")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("a --enable ver"));
+}
+
+#[test]
+fn tags_are_listed_with_counts() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+    fs::write(
+        &config_path,
+        r#"{"aliases":{
+            "gst":{"command_type":{"Simple":"git status"},"created":"2025-10-20","tags":["git"]},
+            "gpf":{"command_type":{"Simple":"git push -f"},"created":"2025-10-20","tags":["git","dangerous"]},
+            "ver":{"command_type":{"Simple":"cargo --version"},"created":"2025-10-20"}
+        }}"#,
+    )
+    .expect("write config");
+
+    cmd.arg("--tags")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("git").and(predicate::str::contains("2 aliases")))
+        .stdout(predicate::str::contains("1 alias\u{1b}"));
+
+    let mut list = Command::cargo_bin("a").expect("binary exists");
+    list.env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .args(["--list", "--tag", "git"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("gpf").and(predicate::str::contains("ver").not()));
+}
+
+#[test]
+fn pinned_alias_needs_typed_confirmation_to_remove() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+    let config = r#"{"aliases":{"deploy":{"command_type":{"Simple":"cargo --version"},"created":"2025-10-20","pinned":true}}}"#;
+    fs::write(&config_path, config).expect("write config");
+
+    cmd.args(["--remove", "deploy", "--force"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("a --unpin deploy"));
+    assert_eq!(
+        fs::read_to_string(&config_path).expect("read config"),
+        config
+    );
+
+    let mut list = Command::cargo_bin("a").expect("binary exists");
+    list.env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .arg("--list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[pinned]"));
+}
+
+#[test]
+fn group_prefix_lists_namespace() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+    fs::write(
+        &config_path,
+        r#"{"aliases":{
+            "git:st":{"command_type":{"Simple":"git status"},"created":"2025-10-20"},
+            "docker:clean":{"command_type":{"Simple":"docker system prune"},"created":"2025-10-20"}
+        }}"#,
+    )
+    .expect("write config");
+
+    cmd.arg("git:")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("git: (1 alias)"))
+        .stdout(predicate::str::contains("docker").not());
+
+    let mut groups = Command::cargo_bin("a").expect("binary exists");
+    groups
+        .env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .args(["--list", "--groups"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("docker:").and(predicate::str::contains("git:")));
+}
+
+#[test]
+fn also_registers_synonyms() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "gst", "echo synonym-ran", "--also", "gs"])
+        .assert()
+        .success();
+
+    let mut run = Command::cargo_bin("a").expect("binary exists");
+    run.env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .arg("gs")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("synonym-ran"));
+
+    let mut list = Command::cargo_bin("a").expect("binary exists");
+    list.env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .arg("--list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("gst (gs)"));
+
+    let mut clash = Command::cargo_bin("a").expect("binary exists");
+    clash
+        .env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .args(["--add", "other", "echo x", "--also", "gst"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("collides with an existing alias"));
+}
+
+#[test]
+fn why_reports_blocking_gate_as_json() {
+    let (mut cmd, home) = command_with_home();
+    let config_path = alias_config_path(&home);
+    fs::write(
+        &config_path,
+        r#"{"aliases":{"gst":{"command_type":{"Simple":"git status"},"created":"2025-10-20","enabled":false}}}"#,
+    )
+    .expect("write config");
+
+    let output = cmd
+        .args(["--why", "gst", "--json"])
+        .output()
+        .expect("run --why");
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(report["runs"], false);
+    assert_eq!(report["blocked_by"], "enabled");
+    assert_eq!(report["gates"][0]["gate"], "exists");
+    assert_eq!(report["gates"][0]["passed"], true);
+}
+
+#[test]
+fn protected_alias_survives_forced_remove() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "deploy", "echo deploy"])
+        .assert()
+        .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["--protect", "deploy"]).success();
+    run(&["--remove", "deploy", "--force"])
+        .failure()
+        .stderr(predicate::str::contains("a --unprotect deploy"));
+    run(&["--list"])
+        .success()
+        .stdout(predicate::str::contains("[protected]"));
+    run(&["--unprotect", "deploy"]).success();
+    run(&["--remove", "deploy", "--force"]).success();
+}
+
+#[test]
+fn append_adds_steps_to_existing_alias() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--append", "missing", "--and", "echo x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Alias 'missing' not found"));
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["--add", "hello", "echo one"]).success();
+    run(&["--append", "hello", "--and", "echo two"])
+        .success()
+        .stdout(predicate::str::contains("echo one && echo two"));
+    run(&["hello"])
+        .success()
+        .stdout(predicate::str::contains("one").and(predicate::str::contains("two")));
+}
+
+#[test]
+fn undo_restores_removed_alias() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "hello", "echo hi"]).assert().success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["--remove", "hello", "--force"]).success();
+    run(&["--undo", "--list"])
+        .success()
+        .stdout(predicate::str::contains("remove 'hello'"));
+    run(&["--undo"])
+        .success()
+        .stdout(predicate::str::contains("restored 'hello' -> echo hi"));
+    run(&["hello"])
+        .success()
+        .stdout(predicate::str::contains("hi"));
+    // The next step back is the original --add
+    run(&["--undo"])
+        .success()
+        .stdout(predicate::str::contains("removed 'hello'"));
+    run(&["--undo"])
+        .failure()
+        .stderr(predicate::str::contains("Nothing to undo."));
+    run(&["--redo"])
+        .success()
+        .stdout(predicate::str::contains("restored 'hello' -> echo hi"));
+}
+
+#[test]
+fn edit_chain_updates_which_output() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "ship", "echo build", "--and", "echo deploy"])
+        .assert()
+        .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["--edit-chain", "ship", "--insert-step", "2", "echo test"]).success();
+    run(&["--edit-chain", "ship", "--delete-step", "9"])
+        .failure()
+        .stderr(predicate::str::contains("step 9 is out of range"));
+    run(&["--which", "ship"])
+        .success()
+        .stdout(predicate::str::contains(
+            "echo build && echo test && echo deploy",
+        ));
+}
+
+#[test]
+fn diff_against_file_uses_exit_codes() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "gst", "git status"]).assert().success();
+    let reviewed = home.path().join("reviewed.json");
+    fs::copy(alias_config_path(&home), &reviewed).expect("copy config");
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .output()
+            .expect("run a")
+    };
+    let reviewed = reviewed.to_string_lossy().to_string();
+    let same = run(&["--diff", "--against", &reviewed, "--format", "json"]);
+    assert_eq!(same.status.code(), Some(0));
+
+    run(&["--add", "extra", "echo extra"]);
+    let drift = run(&["--diff", "--against", &reviewed, "--format", "json"]);
+    assert_eq!(drift.status.code(), Some(1));
+    let report: serde_json::Value = serde_json::from_slice(&drift.stdout).expect("json");
+    assert_eq!(report["added"], serde_json::json!(["extra"]));
+    assert_eq!(report["identical"], false);
+
+    let missing = run(&["--diff", "--against", "/nonexistent/config.json"]);
+    assert_eq!(missing.status.code(), Some(2));
+}
+
+#[test]
+fn stats_json_counts_aliases() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "gst", "git status"]).assert().success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .output()
+            .expect("run a")
+    };
+    run(&["--add", "ci", "echo fmt", "--and", "echo test"]);
+    let output = run(&["--stats", "--json"]);
+    assert!(output.status.success());
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(stats["total"], 2);
+    assert_eq!(stats["chains"], 1);
+    assert_eq!(stats["longest"], serde_json::json!(["ci", 2]));
+    assert_eq!(stats["never_used"], serde_json::json!(["ci", "gst"]));
+}
+
+#[test]
+fn history_lists_runs_and_clears() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "hi", "echo hi"]).assert().success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .output()
+            .expect("run a")
+    };
+    run(&["hi", "there"]);
+    let history = String::from_utf8_lossy(&run(&["--history"]).stdout).to_string();
+    assert!(history.contains("hi"), "{}", history);
+    assert!(history.contains("there"), "{}", history);
+
+    assert!(run(&["--history", "--clear"]).status.success());
+    let cleared = String::from_utf8_lossy(&run(&["--history"]).stdout).to_string();
+    assert!(cleared.contains("No history yet"), "{}", cleared);
+}
+
+#[test]
+fn removed_alias_can_be_restored_from_trash() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "hello", "echo hi"]).assert().success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["--remove", "hello", "--force"]).success();
+    run(&["--trash"])
+        .success()
+        .stdout(predicate::str::contains("hello"))
+        .stdout(predicate::str::contains("echo hi"));
+    run(&["--restore-alias", "hello"])
+        .success()
+        .stdout(predicate::str::contains("Restored alias 'hello'"));
+    run(&["hello"])
+        .success()
+        .stdout(predicate::str::contains("hi"));
+    run(&["--trash"])
+        .success()
+        .stdout(predicate::str::contains("The trash is empty."));
+    run(&["--trash", "--empty", "--than", "soon"]).failure();
+
+    // Undoing a removal takes the alias back out of the trash as well
+    run(&["--remove", "hello", "--force"]).success();
+    run(&["--undo"]).success();
+    run(&["--trash"])
+        .success()
+        .stdout(predicate::str::contains("The trash is empty."));
+    run(&["--restore-alias", "hello"])
+        .failure()
+        .stderr(predicate::str::contains("not in the trash"));
+}
+
+#[test]
+fn params_name_which_example_and_usage_hint() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args([
+        "--add",
+        "greet",
+        "echo $1-$2",
+        "--param",
+        "who",
+        "Person to greet",
+        "--param",
+        "mood",
+        "How they feel",
+    ])
+    .assert()
+    .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["--which", "greet"])
+        .success()
+        .stdout(predicate::str::contains("who mood"))
+        .stdout(predicate::str::contains("echo who-mood"))
+        .stdout(predicate::str::contains("$1  who   Person to greet"));
+    // $2 is needed, so a short run is refused with the usage line
+    run(&["greet", "sam"])
+        .failure()
+        .stderr(predicate::str::contains("Usage: a greet <who> <mood>"));
+    run(&["greet", "sam", "--ignore-arg-check"])
+        .success()
+        .stdout(predicate::str::contains("sam-"))
+        .stderr(predicate::str::contains("Usage: a greet <who> <mood>"));
+}
+
+#[test]
+fn confirm_alias_needs_yes_when_stdin_is_piped() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "nuke", "echo gone $1", "--confirm"])
+        .assert()
+        .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("y\n")
+            .assert()
+    };
+    run(&["--which", "nuke"])
+        .success()
+        .stdout(predicate::str::contains("asks before running"));
+    // A piped answer is not trusted; the alias must not run
+    run(&["nuke", "all"])
+        .failure()
+        .stdout(predicate::str::contains("gone").not())
+        .stderr(predicate::str::contains("pass --yes"));
+    run(&["nuke", "all", "--yes"])
+        .success()
+        .stdout(predicate::str::contains("gone all"));
+}
+
+#[cfg(unix)]
+#[test]
+fn timeout_kills_a_hung_command_with_exit_code_124() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "hang", "sleep 30", "--timeout", "1"])
+        .assert()
+        .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["--which", "hang"])
+        .success()
+        .stdout(predicate::str::contains("1s per command"));
+    run(&["hang"])
+        .code(124)
+        .stderr(predicate::str::contains("killed after 1s"));
+    run(&["--add", "bad", "echo", "--timeout", "0"])
+        .failure()
+        .stderr(predicate::str::contains("--timeout requires"));
+    run(&["--add", "bad", "echo", "--timeout", "18446744073709551615"])
+        .failure()
+        .stderr(predicate::str::contains("--timeout requires"));
+}
+
+#[test]
+fn default_args_fill_missing_positions() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "serve", "echo port $1", "--default", "8080"])
+        .assert()
+        .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["--which", "serve"])
+        .success()
+        .stdout(predicate::str::contains("(defaults: 8080)"))
+        .stdout(predicate::str::contains("echo port 8080"));
+    run(&["serve"])
+        .success()
+        .stdout(predicate::str::contains("port 8080"));
+    run(&["serve", "9000"])
+        .success()
+        .stdout(predicate::str::contains("port 9000"));
+}
+
+#[test]
+fn notes_file_is_shown_by_which_but_not_list() {
+    let (mut cmd, home) = command_with_home();
+    let notes_path = home.path().join("notes.md");
+    std::fs::write(&notes_path, "Needs VPN.\n\nRollback: ./rollback.sh\n").unwrap();
+    cmd.args([
+        "--add",
+        "failover",
+        "echo failing over",
+        "--desc",
+        "Fail over",
+        "--notes-file",
+        notes_path.to_str().unwrap(),
+    ])
+    .assert()
+    .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["--which", "failover"])
+        .success()
+        .stdout(predicate::str::contains("Notes:"))
+        .stdout(predicate::str::contains(
+            "  Needs VPN.\n\n  Rollback: ./rollback.sh",
+        ));
+    run(&["--list"])
+        .success()
+        .stdout(predicate::str::contains("Fail over"))
+        .stdout(predicate::str::contains("Rollback").not());
+}
+
+#[test]
+fn wizard_adds_alias_from_piped_answers() {
+    let (mut cmd, home) = command_with_home();
+    cmd.arg("--wizard")
+        .write_stdin("hello\necho hi\ny\n;\necho bye\nn\nGreets\ny\ny\n")
+        .assert()
+        .success();
+
+    let saved: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(alias_config_path(&home)).unwrap()).unwrap();
+    let chain = &saved["aliases"]["hello"]["command_type"]["Chain"];
+    assert_eq!(chain["parallel"], true);
+    assert_eq!(chain["commands"][1]["command"], "echo bye");
+    assert_eq!(saved["aliases"]["hello"]["description"], "Greets");
+
+    let mut cmd = Command::cargo_bin("a").expect("binary exists");
+    cmd.env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .arg("--wizard")
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Alias not added."));
+}
+
+#[test]
+fn add_from_file_and_stdin_keep_multi_line_commands() {
+    let (mut cmd, home) = command_with_home();
+    let script = home.path().join("greet.txt");
+    std::fs::write(&script, "echo hello $1\r\necho bye\r\n\r\n").unwrap();
+    cmd.args([
+        "--add",
+        "greet",
+        "--from-file",
+        script.to_str().unwrap(),
+        "--desc",
+        "Two lines",
+    ])
+    .assert()
+    .success();
+
+    let saved: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(alias_config_path(&home)).unwrap()).unwrap();
+    assert_eq!(
+        saved["aliases"]["greet"]["command_type"]["Simple"],
+        "echo hello $1\necho bye"
+    );
+    assert_eq!(saved["aliases"]["greet"]["description"], "Two lines");
+
+    let run = |args: &[&str], stdin: &str| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin(stdin)
+            .assert()
+    };
+    run(&["--which", "greet"], "")
+        .success()
+        .stdout(predicate::str::contains("echo hello $1\n    echo bye"))
+        .stdout(predicate::str::contains("Each line runs as its own step"));
+    run(&["greet", "sam"], "")
+        .success()
+        .stdout(predicate::str::contains("hello sam"))
+        .stdout(predicate::str::contains("bye"));
+
+    run(&["--add", "two", "--stdin"], "echo one\necho two\n").success();
+    run(&["two"], "")
+        .success()
+        .stdout(predicate::str::contains("one"))
+        .stdout(predicate::str::contains("two"));
+    run(
+        &["--add", "bad", "--stdin", "--and", "echo x"],
+        "echo one\necho two\n",
+    )
+    .failure()
+    .stderr(predicate::str::contains(
+        "multi-line command cannot be combined",
+    ));
+}
+
+#[test]
+fn add_checks_parsing_and_warns_about_missing_programs() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "broken", "echo \"unterminated"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be parsed"))
+        .stderr(predicate::str::contains("--force-invalid"));
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["--add", "broken", "echo \"unterminated", "--force-invalid"]).success();
+    run(&["--add", "later", "no-such-tool-abc123 run"])
+        .success()
+        .stdout(predicate::str::contains(
+            "'no-such-tool-abc123' was not found in PATH",
+        ))
+        .stdout(predicate::str::contains("Added alias 'later'"));
+}
+
+#[test]
+fn named_placeholders_bind_by_position_or_by_name() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "deploy", "echo ${image}:${tag} costs $${x}"])
+        .assert()
+        .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["deploy", "myapp", "latest"])
+        .success()
+        .stdout(predicate::str::contains("myapp:latest costs ${x}"));
+    run(&["deploy", "tag=v2", "image=web"])
+        .success()
+        .stdout(predicate::str::contains("web:v2"));
+    run(&["deploy", "tag=v2"])
+        .failure()
+        .stderr(predicate::str::contains("needs 2 arguments, got 0"))
+        .stderr(predicate::str::contains("Usage: a deploy <image> <tag>"));
+    run(&["--which", "deploy"])
+        .success()
+        .stdout(predicate::str::contains("image ($1), tag ($2)"));
+}
+
+#[test]
+fn which_shows_argument_slices_and_count() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "kc", "echo env=$1 n=$# rest=${@:2}"])
+        .assert()
+        .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["--which", "kc"])
+        .success()
+        .stdout(predicate::str::contains("($# = 3, ${@:2} = arg2 arg3)"));
+    run(&["kc", "prod", "get", "pods"])
+        .success()
+        .stdout(predicate::str::contains("env=prod n=3 rest=get pods"));
+}
+
+#[test]
+fn which_previews_date_tokens() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "stamp", "echo backup-$DATE"])
+        .assert()
+        .success();
+
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["--which", "stamp"])
+        .success()
+        .stdout(predicate::str::contains(format!("$DATE = {}", today)));
+    run(&["stamp", "now"])
+        .success()
+        .stdout(predicate::str::contains(format!("backup-{} now", today)));
+}
+
+#[test]
+fn prompt_missing_fails_fast_without_a_terminal() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "gpush", "echo push origin $1", "--prompt-missing"])
+        .assert()
+        .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+    run(&["--which", "gpush"])
+        .success()
+        .stdout(predicate::str::contains(
+            "asks for missing arguments on a terminal",
+        ));
+    run(&["gpush"])
+        .failure()
+        .stderr(predicate::str::contains("'gpush' needs 1 argument, got 0"))
+        .stderr(predicate::str::contains("Usage: a gpush <arg1>"))
+        .stderr(predicate::str::contains("Value for").not());
+    run(&["gpush", "main"])
+        .success()
+        .stdout(predicate::str::contains("push origin main"));
+}
+
+#[test]
+fn param_patterns_reject_bad_arguments() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args([
+        "--add",
+        "rel",
+        "echo tagging $1",
+        "--param",
+        "version",
+        "Release version",
+        "--pattern",
+        r"^v\d+\.\d+\.\d+$",
+        "--example",
+        "v1.2.3",
+    ])
+    .assert()
+    .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .assert()
+    };
+    run(&["rel", "1.2"])
+        .failure()
+        .stderr(predicate::str::contains(
+            "Argument $1 (version) '1.2' does not match",
+        ))
+        .stderr(predicate::str::contains("(e.g. v1.2.3)"));
+    run(&["rel", "v1.2.3"])
+        .success()
+        .stdout(predicate::str::contains("tagging v1.2.3"));
+    run(&["--which", "rel"])
+        .success()
+        .stdout(predicate::str::contains(r"(pattern: ^v\d+\.\d+\.\d+$"));
+    run(&[
+        "--add",
+        "x",
+        "echo $1",
+        "--param",
+        "v",
+        "d",
+        "--pattern",
+        "([",
+    ])
+    .failure()
+    .stderr(predicate::str::contains("invalid --pattern for 'v'"));
+    run(&["--add", "y", "echo $1", "--pattern", "x"])
+        .failure()
+        .stderr(predicate::str::contains("applies to the --param before it"));
+}
+
+#[test]
+fn glob_aliases_expand_patterns_in_the_working_directory() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "show", "echo", "--glob"])
+        .assert()
+        .success();
+    let work = home.path().join("work dir");
+    fs::create_dir(&work).unwrap();
+    fs::write(work.join("a b.log"), "").unwrap();
+    fs::write(work.join("c.log"), "").unwrap();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .current_dir(&work)
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+    run(&["show", "*.log"])
+        .success()
+        .stdout(predicate::str::contains("a b.log c.log"));
+    run(&["show", "*.gz"])
+        .success()
+        .stdout(predicate::str::contains("*.gz"));
+    run(&["--set", "exec.failglob", "true"]).success();
+    run(&["--which", "show"])
+        .success()
+        .stdout(predicate::str::contains("matches nothing is an error"));
+    run(&["show", "*.gz"])
+        .failure()
+        .stderr(predicate::str::contains("No files match '*.gz'"));
+}
+
+#[test]
+fn capture_step_output_feeds_later_steps() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args([
+        "--add",
+        "dtag",
+        "echo preparing",
+        "--capture",
+        "SHA",
+        "echo 1a2b3c4",
+        "--and",
+        "echo tagging app:$SHA",
+    ])
+    .assert()
+    .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+    run(&["--which", "dtag"])
+        .success()
+        .stdout(predicate::str::contains("captures stdout as $SHA"));
+    run(&["dtag"])
+        .success()
+        .stdout(predicate::str::contains("tagging app:1a2b3c4"))
+        .stdout(predicate::str::contains("\n1a2b3c4").not());
+
+    let (mut cmd, _home) = command_with_home();
+    cmd.args([
+        "--add",
+        "both",
+        "echo a",
+        "--capture",
+        "X",
+        "--and",
+        "echo $X",
+        "--parallel",
+    ])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains(
+        "--capture cannot be used with --parallel",
+    ));
+}
+
+#[test]
+fn config_variables_are_shared_by_aliases() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "where", "echo pushing to {{REGISTRY}}/$1"])
+        .assert()
+        .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+    run(&["where", "api"])
+        .failure()
+        .stderr(predicate::str::contains("Undefined variable 'REGISTRY'"));
+    run(&["--which", "where"])
+        .success()
+        .stdout(predicate::str::contains("REGISTRY (undefined)"));
+
+    run(&["--set-var", "REGISTRY", "ghcr.io/acme"]).success();
+    run(&["--vars"])
+        .success()
+        .stdout(predicate::str::contains("= ghcr.io/acme"));
+    run(&["where", "api"])
+        .success()
+        .stdout(predicate::str::contains("pushing to ghcr.io/acme/api"));
+    run(&["--which", "where"])
+        .success()
+        .stdout(predicate::str::contains("REGISTRY = ghcr.io/acme"))
+        .stdout(predicate::str::contains("echo pushing to ghcr.io/acme/"));
+
+    run(&["--unset-var", "REGISTRY"]).success();
+    run(&["--vars"])
+        .success()
+        .stdout(predicate::str::contains("No variables defined"));
+}
+
+#[test]
+fn dry_run_shows_steps_without_running_them() {
+    let (mut cmd, home) = command_with_home();
+    let marker = home.path().join("ran");
+    let touch = format!("touch {}", marker.display());
+    cmd.args(["--add", "ship", "echo shipping $1", "--and", touch.as_str()])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("a").expect("binary exists");
+    cmd.env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .args(["ship", "--dry-run", "v1.2"])
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("echo shipping v1.2"))
+        .stdout(predicate::str::contains("touch"))
+        .stdout(predicate::str::contains("\nshipping v1.2").not());
+    assert!(!marker.exists());
+
+    // Accepted anywhere after the alias name, including after its arguments
+    let mut cmd = Command::cargo_bin("a").expect("binary exists");
+    cmd.env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .args(["ship", "v1.2", "--dry-run"])
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("echo shipping v1.2"))
+        .stdout(predicate::str::contains("touch"))
+        .stdout(predicate::str::contains("--dry-run").not())
+        .stdout(predicate::str::contains("\nshipping v1.2").not());
+    assert!(!marker.exists());
+}
+
+#[test]
+fn print_emits_only_the_resolved_command() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "greet", "echo hello $1"])
+        .assert()
+        .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+    run(&["--print", "greet", "big world"])
+        .success()
+        .stdout("echo hello 'big world'\n");
+    run(&["--print", "nope"])
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("Alias 'nope' not found"));
+}
+
+#[test]
+fn verbose_echoes_commands_on_stderr_only() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "shout", "echo loud $1"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("a").expect("binary exists");
+    cmd.env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .args(["shout", "--verbose", "it out"])
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("loud it out"))
+        .stdout(predicate::str::contains("+ ").not())
+        .stderr(predicate::str::contains("+ echo loud 'it out'"))
+        .stderr(predicate::str::contains("exit code 0 after"));
+}
+
+#[test]
+fn quiet_leaves_only_the_commands_output() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "pair", "echo one", "--and", "echo two $@"])
+        .assert()
+        .success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+    run(&["pair", "x"])
+        .success()
+        .stderr(predicate::str::contains("Executing"));
+    run(&["pair", "-q", "x"])
+        .success()
+        .stdout("one x\ntwo x\n")
+        .stderr("");
+    // -q works after the arguments too; after --, it belongs to the command
+    run(&["pair", "x", "-q"])
+        .success()
+        .stdout("one x\ntwo x\n")
+        .stderr("");
+    run(&["pair", "--", "-q"])
+        .success()
+        .stdout(predicate::str::contains("two -q"));
+
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "hush", "echo one", "--and", "echo two", "--quiet"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("a").expect("binary exists");
+    cmd.env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .arg("hush")
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout("one\ntwo\n")
+        .stderr("");
+}
+
+#[test]
+fn chain_progress_goes_to_stderr_so_stdout_can_be_piped() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args([
+        "--add",
+        "token",
+        "echo s3cr3t",
+        "--or",
+        "echo unused",
+        "--always",
+        "echo -n",
+    ])
+    .assert()
+    .success();
+
+    let mut cmd = Command::cargo_bin("a").expect("binary exists");
+    cmd.env("HOME", home.path())
+        .env("USERPROFILE", home.path())
+        .arg("token")
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout("s3cr3t\n")
+        .stderr(predicate::str::contains("[1/3]"))
+        .stderr(predicate::str::contains("Skipping"))
+        .stderr(predicate::str::contains(
+            "Sequential command chain completed",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn chain_failures_set_the_process_exit_status() {
+    let (_cmd, home) = command_with_home();
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+
+    run(&[
+        "--add",
+        "ship",
+        "sh -c 'exit 3'",
+        "--and",
+        "echo deploy",
+        "--always",
+        "echo cleanup",
+    ])
+    .success();
+    run(&["ship"])
+        .code(3)
+        .stdout(predicate::str::contains("cleanup"));
+
+    run(&["--add", "retry", "false", "--or", "true"]).success();
+    run(&["retry"]).code(0);
+
+    // The first failure in chain order wins, even if it finishes last
+    run(&[
+        "--add",
+        "both",
+        "sh -c 'sleep 1; exit 4'",
+        "--and",
+        "sh -c 'exit 5'",
+        "--parallel",
+    ])
+    .success();
+    run(&["both"]).code(4);
+}
+
+#[test]
+fn strict_stops_a_chain_at_a_step_that_cannot_run() {
+    let (_cmd, home) = command_with_home();
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+
+    run(&[
+        "--add",
+        "deploy",
+        "a-missing-program-xyz",
+        "--or",
+        "echo rollback",
+    ])
+    .success();
+    run(&["deploy"])
+        .success()
+        .stdout("rollback\n")
+        .stderr(predicate::str::contains("step 1/2 could not run"));
+    run(&["deploy", "--strict"])
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("stopped at step 1/2"));
+
+    run(&[
+        "--add",
+        "deploy",
+        "a-missing-program-xyz",
+        "--or",
+        "echo rollback",
+        "--strict",
+        "--force",
+    ])
+    .success();
+    run(&["--which", "deploy"])
+        .success()
+        .stdout(predicate::str::contains(
+            "a step that cannot run stops the chain",
+        ));
+    run(&["deploy"]).code(1).stdout("");
+}
+
+#[test]
+fn jobs_limits_how_many_parallel_commands_run_at_once() {
+    let (_cmd, home) = command_with_home();
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+
+    run(&[
+        "--add", "seq", "echo one", "--and", "echo two", "--jobs", "2",
+    ])
+    .failure()
+    .stderr(predicate::str::contains("add --parallel"));
+    run(&[
+        "--add",
+        "both",
+        "echo one",
+        "--and",
+        "echo two",
+        "--and",
+        "echo three",
+        "--parallel",
+        "--jobs",
+        "2",
+    ])
+    .success();
+    run(&["--which", "both"])
+        .success()
+        .stdout(predicate::str::contains("PARALLEL (-j 2)"));
+    run(&["both"])
+        .success()
+        .stderr(predicate::str::contains("at most 2 at a time"));
+    run(&["both", "-j", "1"])
+        .success()
+        .stderr(predicate::str::contains("at most 1 at a time"))
+        .stderr(predicate::str::contains("Step  Command"));
+    run(&["both", "--no-summary"])
+        .success()
+        .stderr(predicate::str::contains("Step  Command").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn parallel_output_is_tagged_with_the_step_number() {
+    let (_cmd, home) = command_with_home();
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+
+    run(&[
+        "--add",
+        "both",
+        "echo one",
+        "--and",
+        "sh -c 'echo two; echo warn >&2'",
+        "--parallel",
+    ])
+    .success();
+    run(&["both"])
+        .success()
+        .stdout(predicate::str::contains("[1]\u{1b}[0m one\n"))
+        .stdout(predicate::str::contains("[2]\u{1b}[0m two\n"))
+        .stderr(predicate::str::contains("[2]\u{1b}[0m warn\n"));
+
+    let plain = run(&["both", "--no-prefix"]).success();
+    let stdout = String::from_utf8(plain.get_output().stdout.clone()).unwrap();
+    let mut lines: Vec<&str> = stdout.lines().collect();
+    lines.sort();
+    assert_eq!(lines, vec!["one", "two"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn interactive_parallel_steps_keep_the_terminal() {
+    let (_cmd, home) = command_with_home();
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+
+    for extra in [
+        &["--and", "echo two", "--parallel", "--ordered"][..],
+        &["--pipe", "cat"],
+        &["--capture", "out"],
+    ] {
+        let mut args = vec!["--add", "tty", "echo one", "--interactive"];
+        args.extend_from_slice(extra);
+        run(&args).failure().stderr(predicate::str::contains(
+            "--interactive programs need the terminal",
+        ));
+    }
+
+    run(&[
+        "--add",
+        "tty",
+        "echo one",
+        "--and",
+        "echo two",
+        "--parallel",
+        "--interactive",
+    ])
+    .success();
+    let output = run(&["tty"]).success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let mut lines: Vec<&str> = stdout.lines().collect();
+    lines.sort();
+    assert_eq!(lines, vec!["one", "two"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn ordered_parallel_output_comes_out_in_step_order() {
+    let (_cmd, home) = command_with_home();
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+
+    run(&["--add", "seq", "echo one", "--and", "echo two", "--ordered"])
+        .failure()
+        .stderr(predicate::str::contains("add --parallel"));
+    run(&[
+        "--add",
+        "both",
+        "sh -c 'sleep 0.3; echo slow'",
+        "--and",
+        "echo fast",
+        "--parallel",
+        "--ordered",
+    ])
+    .success();
+    run(&["--which", "both"])
+        .success()
+        .stdout(predicate::str::contains("PARALLEL (ordered)"));
+    run(&["both"])
+        .success()
+        .stdout("slow\nfast\n")
+        .stderr(predicate::str::contains(
+            "echo fast\u{1b}[0m \u{1b}[90m(exit code 0 after",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn parallel_stages_run_after_the_stage_before() {
+    let (_cmd, home) = command_with_home();
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+
+    run(&[
+        "--add",
+        "ci",
+        "false",
+        "--and",
+        "echo built",
+        "--or",
+        "echo recovering",
+        "--parallel",
+        "--ordered",
+    ])
+    .success();
+    run(&["--which", "ci"])
+        .success()
+        .stdout(predicate::str::contains(
+            "PARALLEL (ordered): false & echo built || echo recovering",
+        ))
+        .stdout(predicate::str::contains(
+            "stage 2, run if the previous stage failed",
+        ));
+    run(&["ci"])
+        .success()
+        .stdout("built\nrecovering\n")
+        .stderr(predicate::str::contains("[stage 2/2]"));
+    run(&["--print", "ci"])
+        .failure()
+        .stderr(predicate::str::contains("no shell equivalent"));
+}
+
+#[cfg(unix)]
+#[test]
+fn pipe_feeds_one_step_into_the_next() {
+    let (_cmd, home) = command_with_home();
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+
+    run(&[
+        "--add",
+        "count",
+        "printf 'a\\nb\\nc\\n'",
+        "--pipe",
+        "grep -v b",
+        "--pipe",
+        "wc -l",
+    ])
+    .success();
+    run(&["--which", "count"])
+        .success()
+        .stdout(predicate::str::contains(
+            "printf 'a\\nb\\nc\\n' | grep -v b | wc -l",
+        ))
+        .stdout(predicate::str::contains("read the previous step's output"));
+    run(&["count"])
+        .success()
+        .stdout(predicate::str::is_match(r"^\s*2\n$").unwrap());
+
+    // The pipeline exits with its last command's code
+    run(&["--add", "last", "false", "--pipe", "sh -c 'cat; exit 4'"]).success();
+    run(&["last"]).code(4);
+
+    // A command of the pipeline that cannot start is reported as its own step
+    run(&[
+        "--add",
+        "typo",
+        "echo hi",
+        "--pipe",
+        "a-missing-program-xyz",
+    ])
+    .success();
+    run(&["typo"])
+        .code(127)
+        .stderr(predicate::str::contains("step 2/2 could not run"));
+
+    run(&["--add", "both", "echo x", "--pipe", "cat", "--parallel"])
+        .failure()
+        .stderr(predicate::str::contains("--parallel chain cannot do"));
+}
+
+#[cfg(unix)]
+#[test]
+fn double_dash_passes_runtime_flags_on_to_the_command() {
+    let (mut cmd, home) = command_with_home();
+    cmd.args(["--add", "mk", "printf [%s]"]).assert().success();
+
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("a").expect("binary exists");
+        cmd.env("HOME", home.path())
+            .env("USERPROFILE", home.path())
+            .args(args)
+            .write_stdin("")
+            .assert()
+    };
+    run(&["mk", "--", "-j8"]).success().stdout("[-j8]");
+    run(&["mk", "all", "--", "-j8"])
+        .success()
+        .stdout("[all][-j8]");
+    run(&["mk", "all", "-j", "abc"])
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("invalid value for -j: abc"));
+}